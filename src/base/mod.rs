@@ -6,20 +6,32 @@ use v8::OwnedIsolate;
 use v8::{Global, Local};
 
 use std::error::Error;
+use std::rc::Rc;
 
+use crate::core::runtime::JsError;
 use crate::inspect::inspect_v8_value;
 use crate::utils::initialize_v8;
 
+mod module;
+
+pub use module::ModuleLoader;
+pub use module::ModuleMap;
+pub use module::ModuleSource;
+pub use module::NoopModuleLoader;
+
 #[derive(Debug, PartialEq)]
 pub enum EvalError {
     CompileError,
-    RuntimeError,
+    RuntimeError(JsError),
     ConversionError,
 }
 
 impl std::fmt::Display for EvalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            EvalError::RuntimeError(err) => write!(f, "RuntimeError: {}", err),
+            other => write!(f, "{:?}", other),
+        }
     }
 }
 
@@ -37,6 +49,73 @@ pub struct JsContext {
 
 pub struct JsState {
     pub handler: Option<Global<v8::Function>>,
+    pub last_exception: Option<JsError>,
+    pub loader: Rc<dyn ModuleLoader>,
+    pub modules: ModuleMap,
+}
+
+/// `import('specifier')`: resolves and loads the module through the
+/// context's `ModuleLoader`, synchronously (`JsContext` has no event loop to
+/// defer to), and settles the promise JS is waiting on.
+extern "C" fn host_import_module_dynamically_callback<'s>(
+    scope: &mut HandleScope<'s>,
+    _host_defined_options: Local<'s, v8::Data>,
+    resource_name: Local<'s, v8::Value>,
+    specifier: Local<'s, v8::String>,
+    _import_assertions: Local<'s, v8::FixedArray>,
+) -> Option<Local<'s, v8::Promise>> {
+    let resolver = v8::PromiseResolver::new(scope)?;
+    let promise = resolver.get_promise(scope);
+
+    let referrer = resource_name.to_rust_string_lossy(scope);
+    let specifier = specifier.to_rust_string_lossy(scope);
+
+    match JsContext::resolve_and_evaluate_module(scope, &specifier, &referrer) {
+        Ok(value) => {
+            resolver.resolve(scope, value);
+        }
+        Err(message) => {
+            let message = v8::String::new(scope, &message).unwrap();
+            resolver.reject(scope, message.into());
+        }
+    }
+
+    Some(promise)
+}
+
+/// Looks up the canonical URL a `Module` was registered under, so the
+/// `ResolveModuleCallback` below can resolve a static import relative to
+/// the module that requested it.
+extern "C" fn resolve_module_callback<'s>(
+    context: Local<'s, Context>,
+    specifier: Local<'s, v8::String>,
+    _import_assertions: Local<'s, v8::FixedArray>,
+    referrer: Local<'s, v8::Module>,
+) -> Option<Local<'s, v8::Module>> {
+    let scope = &mut unsafe { v8::CallbackScope::new(context) };
+    let specifier = specifier.to_rust_string_lossy(scope);
+
+    let (loader, referrer_url) = {
+        let state = scope.get_slot::<JsState>()?;
+        let referrer_url = state.modules.url_of(referrer.get_identity_hash())?.to_string();
+        (state.loader.clone(), referrer_url)
+    };
+
+    let url = loader.resolve(&specifier, &referrer_url).ok()?;
+    let state = scope.get_slot::<JsState>()?;
+    state.modules.get(&url).map(|module| Local::new(scope, module))
+}
+
+/// Wrap a module-loading/compilation failure (a plain message — there's no
+/// live `TryCatch` to pull a stack trace from during module resolution) as
+/// an `EvalError::RuntimeError`, matching the richer `JsError` model used
+/// everywhere else uncaught JS errors surface.
+fn module_error(message: String) -> EvalError {
+    EvalError::RuntimeError(JsError {
+        message,
+        stack: None,
+        frames: Vec::new(),
+    })
 }
 
 impl JsContext {
@@ -46,6 +125,9 @@ impl JsContext {
 
         let mut isolate = Isolate::new(Default::default());
 
+        isolate.set_capture_stack_trace_for_uncaught_exceptions(true, 10);
+        isolate.set_host_import_module_dynamically_callback(host_import_module_dynamically_callback);
+
         let context = {
             // let mut isolate = &runtime.isolate;
             let scope = &mut HandleScope::new(&mut isolate);
@@ -61,7 +143,12 @@ impl JsContext {
                 global.delete(scope, console_key.into());
             }
 
-            scope.set_slot(JsState { handler: None });
+            scope.set_slot(JsState {
+                handler: None,
+                last_exception: None,
+                loader: module::default_loader(),
+                modules: ModuleMap::new(),
+            });
 
             let context = Global::new(scope, context);
 
@@ -82,6 +169,161 @@ impl JsContext {
         context
     }
 
+    /// Use a custom [`ModuleLoader`] for `import`/`import()` instead of the
+    /// default one, which rejects every specifier.
+    pub fn set_module_loader(&mut self, loader: Rc<dyn ModuleLoader>) {
+        let scope = &mut HandleScope::new(&mut self.isolate);
+        let context = Local::new(scope, &self.context);
+        let scope = &mut ContextScope::new(scope, context);
+
+        let state = scope.get_slot_mut::<JsState>().expect("Missing runtime data in V8 context");
+        state.loader = loader;
+    }
+
+    /// Compile `code` as an ES module, resolve and instantiate its static
+    /// imports through the context's `ModuleLoader`, and evaluate it.
+    pub fn eval_module(&mut self, url: &str, code: &str) -> Result<String, EvalError> {
+        let scope = &mut HandleScope::new(&mut self.isolate);
+        let context = Local::new(scope, &self.context);
+        let scope = &mut ContextScope::new(scope, context);
+
+        let value = Self::evaluate_module_source(scope, url, code).map_err(module_error)?;
+
+        Ok(value.to_rust_string_lossy(scope))
+    }
+
+    /// Resolve `specifier` against `referrer` through the `JsState` loader
+    /// and run the compile-instantiate-evaluate sequence on the result.
+    /// Used by the dynamic `import()` callback, which only has a specifier
+    /// and referrer URL to start from.
+    fn resolve_and_evaluate_module<'s>(
+        scope: &mut ContextScope<'s, HandleScope<'s>>,
+        specifier: &str,
+        referrer: &str,
+    ) -> Result<Local<'s, v8::Value>, String> {
+        let state = scope.get_slot::<JsState>().unwrap();
+        let loader = state.loader.clone();
+        let url = loader.resolve(specifier, referrer)?;
+        let source = loader.load(&url)?;
+
+        Self::evaluate_module_source(scope, &source.url, &source.code)
+    }
+
+    /// Compile `code` as a module (registering it and its transitive static
+    /// imports in the `ModuleMap`), instantiate it, and evaluate it. Shared
+    /// by [`Self::eval_module`], [`Self::load_main_module`] and
+    /// [`Self::resolve_and_evaluate_module`], which all need the same
+    /// load-compile-instantiate-evaluate sequence once source text is in hand.
+    ///
+    /// A URL that's already been evaluated through this function (e.g.
+    /// `eval_module`'d twice, or `import()`ed after being loaded as the main
+    /// module) returns its cached completion value instead of calling
+    /// `Module::evaluate` again. Only URLs passed in here are recorded: a
+    /// module evaluated solely as another module's static import is not, so
+    /// a later `import()` of it goes through instantiate and evaluate.
+    fn evaluate_module_source<'s>(
+        scope: &mut ContextScope<'s, HandleScope<'s>>,
+        url: &str,
+        code: &str,
+    ) -> Result<Local<'s, v8::Value>, String> {
+        let mut module = Self::compile_and_register_module(scope, url, code)?;
+
+        if let Some(value) = scope.get_slot::<JsState>().unwrap().modules.evaluated_value(url) {
+            return Ok(Local::new(scope, value));
+        }
+
+        module
+            .instantiate_module(scope, resolve_module_callback)
+            .ok_or_else(|| format!("failed to instantiate module \"{url}\""))?;
+
+        let value = module
+            .evaluate(scope)
+            .ok_or_else(|| format!("failed to evaluate module \"{url}\""))?;
+
+        let global = Global::new(scope, value);
+        scope
+            .get_slot_mut::<JsState>()
+            .unwrap()
+            .modules
+            .mark_evaluated(url, global);
+
+        Ok(value)
+    }
+
+    /// Compile `code` as a module, recursively compiling and registering any
+    /// modules it statically imports so [`resolve_module_callback`] can find
+    /// them during instantiation.
+    fn compile_and_register_module<'s>(
+        scope: &mut ContextScope<'s, HandleScope<'s>>,
+        url: &str,
+        code: &str,
+    ) -> Result<Local<'s, v8::Module>, String> {
+        if let Some(module) = scope.get_slot::<JsState>().unwrap().modules.get(url) {
+            return Ok(Local::new(scope, module));
+        }
+
+        let source_text =
+            v8::String::new(scope, code).ok_or_else(|| "invalid module source".to_string())?;
+        let resource_name: Local<v8::Value> = v8::String::new(scope, url)
+            .ok_or_else(|| "invalid module url".to_string())?
+            .into();
+        let origin = v8::ScriptOrigin::new(
+            scope,
+            resource_name,
+            0,
+            0,
+            false,
+            0,
+            None,
+            false,
+            false,
+            true,
+        );
+        let source = v8::script_compiler::Source::new(source_text, Some(&origin));
+
+        let module = v8::script_compiler::compile_module(scope, source)
+            .ok_or_else(|| format!("failed to compile module \"{url}\""))?;
+
+        let hash = module.get_identity_hash();
+        let global = Global::new(scope, module);
+        scope
+            .get_slot_mut::<JsState>()
+            .unwrap()
+            .modules
+            .insert(url.to_string(), hash, global);
+
+        let requests = module.get_module_requests(scope);
+        for i in 0..requests.length() {
+            let request: Local<v8::ModuleRequest> =
+                requests.get(scope, i).unwrap().try_into().unwrap();
+            let dep_specifier = request.get_specifier(scope).to_rust_string_lossy(scope);
+
+            let state = scope.get_slot::<JsState>().unwrap();
+            let loader = state.loader.clone();
+            let dep_url = loader.resolve(&dep_specifier, url)?;
+            let dep_source = loader.load(&dep_url)?;
+
+            Self::compile_and_register_module(scope, &dep_source.url, &dep_source.code)?;
+        }
+
+        Ok(module)
+    }
+
+    /// Load, instantiate and evaluate `url` as the worker's entry module.
+    pub fn load_main_module(&mut self, url: &str) -> Result<String, EvalError> {
+        let scope = &mut HandleScope::new(&mut self.isolate);
+        let context = Local::new(scope, &self.context);
+        let scope = &mut ContextScope::new(scope, context);
+
+        let loader = scope.get_slot::<JsState>().unwrap().loader.clone();
+        let source = loader.load(url).map_err(module_error)?;
+
+        let value =
+            Self::evaluate_module_source(scope, &source.url, &source.code).map_err(module_error)?;
+
+        Ok(value.to_rust_string_lossy(scope))
+    }
+
     /// Register a new extension
     pub fn register<E: JsExt>(&mut self, ext: &E) {
         let scope = &mut HandleScope::new(&mut self.isolate);
@@ -91,8 +333,15 @@ impl JsContext {
         ext.bind(scope);
     }
 
+    /// Take the last uncaught exception captured by [`Self::eval`] or
+    /// [`Self::fetch`], if any. Reading it clears the slot.
     pub fn last_exception(&mut self) -> Option<String> {
-        None // TODO
+        let scope = &mut HandleScope::new(&mut self.isolate);
+        let context = Local::new(scope, &self.context);
+        let scope = &mut ContextScope::new(scope, context);
+
+        let state = scope.get_slot_mut::<JsState>().expect("Missing runtime data in V8 context");
+        state.last_exception.take().map(|err| err.message)
     }
 
     /// Evaluate a script
@@ -101,12 +350,27 @@ impl JsContext {
 
         let context = Local::new(scope, &self.context);
         let scope = &mut ContextScope::new(scope, context);
+        let scope = &mut v8::TryCatch::new(scope);
 
         let code = v8::String::new(scope, script).ok_or(EvalError::CompileError)?;
         let script = v8::Script::compile(scope, code, None).ok_or(EvalError::CompileError)?;
 
         // Run script
-        let result = script.run(scope).ok_or(EvalError::RuntimeError)?;
+        let result = match script.run(scope) {
+            Some(result) => result,
+            None => {
+                let err = JsError::from_try_catch(scope).unwrap_or(JsError {
+                    message: "unknown error".to_string(),
+                    stack: None,
+                    frames: Vec::new(),
+                });
+
+                let state = scope.get_slot_mut::<JsState>().expect("Missing runtime data in V8 context");
+                state.last_exception = Some(err.clone());
+
+                return Err(EvalError::RuntimeError(err));
+            }
+        };
 
         let result = result.to_string(scope).ok_or(EvalError::ConversionError)?;
 
@@ -119,6 +383,7 @@ impl JsContext {
 
         let context = Local::new(scope, &self.context);
         let scope = &mut ContextScope::new(scope, context);
+        let scope = &mut v8::TryCatch::new(scope);
 
         // Check if script registered event listeners
         let handler = {
@@ -135,13 +400,22 @@ impl JsContext {
             }
         };
 
-        if handler.is_none() {
-            return None;
-        }
+        let handler = handler?;
 
-        let handler = Local::new(scope, handler.unwrap());
+        let handler = Local::new(scope, handler);
         let undefined = v8::undefined(scope).into();
-        let result = handler.call(scope, undefined, &[undefined]).unwrap();
+
+        let result = match handler.call(scope, undefined, &[undefined]) {
+            Some(result) => result,
+            None => {
+                let state = scope
+                    .get_slot_mut::<JsState>()
+                    .expect("Missing runtime data in V8 context");
+                state.last_exception = JsError::from_try_catch(scope);
+                return None;
+            }
+        };
+
         println!("event result: {:?}", inspect_v8_value(result, scope));
         Some(result.to_string(scope).unwrap().to_rust_string_lossy(scope))
     }
@@ -174,8 +448,7 @@ mod tests {
 
         let result = ctx.eval("throw new Error('test')");
 
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), EvalError::RuntimeError);
+        assert!(matches!(result.unwrap_err(), EvalError::RuntimeError(_)));
     }
 
     /// eval should not panic when js exception is thrown
@@ -189,13 +462,69 @@ mod tests {
         assert_eq!(result.unwrap_err(), EvalError::CompileError);
     }
 
+    /// `import()` is now a valid expression: it synchronously evaluates to a
+    /// promise (rejected, here, since no `ModuleLoader` is configured) rather
+    /// than failing to compile.
     #[test]
     fn eval_should_not_panic_on_dynamic_import() {
         let mut ctx = prepare_context();
 
         let result = ctx.eval("import('moduleName')");
 
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), EvalError::CompileError);
+        assert_eq!(result.unwrap(), String::from("[object Promise]"));
+    }
+
+    /// A module with no imports should load, instantiate and evaluate
+    /// end-to-end through a custom `ModuleLoader`.
+    #[test]
+    fn eval_module_should_run_without_imports() {
+        struct SingleModuleLoader;
+
+        impl crate::base::ModuleLoader for SingleModuleLoader {
+            fn resolve(&self, specifier: &str, _referrer: &str) -> Result<String, String> {
+                Ok(specifier.to_string())
+            }
+
+            fn load(&self, url: &str) -> Result<crate::base::ModuleSource, String> {
+                Ok(crate::base::ModuleSource {
+                    url: url.to_string(),
+                    code: String::from("export const value = 1 + 1;"),
+                })
+            }
+        }
+
+        let mut ctx = prepare_context();
+        ctx.set_module_loader(std::rc::Rc::new(SingleModuleLoader));
+
+        let result = ctx.eval_module("main.js", "export const value = 1 + 1;");
+
+        assert!(result.is_ok());
+    }
+
+    /// Evaluating the same module URL twice through `eval_module` must
+    /// return the cached result rather than calling V8's `Module::evaluate`
+    /// on it a second time.
+    #[test]
+    fn eval_module_should_not_reevaluate_on_repeated_load() {
+        struct SingleModuleLoader;
+
+        impl crate::base::ModuleLoader for SingleModuleLoader {
+            fn resolve(&self, specifier: &str, _referrer: &str) -> Result<String, String> {
+                Ok(specifier.to_string())
+            }
+
+            fn load(&self, url: &str) -> Result<crate::base::ModuleSource, String> {
+                Ok(crate::base::ModuleSource {
+                    url: url.to_string(),
+                    code: String::from("export const value = 1 + 1;"),
+                })
+            }
+        }
+
+        let mut ctx = prepare_context();
+        ctx.set_module_loader(std::rc::Rc::new(SingleModuleLoader));
+
+        assert!(ctx.eval_module("main.js", "export const value = 1 + 1;").is_ok());
+        assert!(ctx.eval_module("main.js", "export const value = 1 + 1;").is_ok());
     }
 }
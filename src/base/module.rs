@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// A loaded module's source text and canonical URL, returned by a
+/// [`ModuleLoader`].
+pub struct ModuleSource {
+    pub url: String,
+    pub code: String,
+}
+
+/// Resolves and loads ES modules for `import`/`import()`, modeled on
+/// deno_core's `ModuleLoader`.
+pub trait ModuleLoader {
+    /// Resolve `specifier` against `referrer` into a canonical module URL.
+    fn resolve(&self, specifier: &str, referrer: &str) -> Result<String, String>;
+
+    /// Fetch the source text for an already-resolved module URL.
+    fn load(&self, url: &str) -> Result<ModuleSource, String>;
+}
+
+/// Default loader installed by [`super::JsContext::create`]: there's no
+/// module graph to resolve against until the host wires one up, so every
+/// import is rejected with a clear message rather than panicking.
+pub struct NoopModuleLoader;
+
+impl ModuleLoader for NoopModuleLoader {
+    fn resolve(&self, specifier: &str, _referrer: &str) -> Result<String, String> {
+        Err(format!(
+            "module loading is not configured (tried to resolve \"{specifier}\")"
+        ))
+    }
+
+    fn load(&self, url: &str) -> Result<ModuleSource, String> {
+        Err(format!(
+            "module loading is not configured (tried to load \"{url}\")"
+        ))
+    }
+}
+
+/// Tracks instantiated modules by canonical URL, like deno_core's
+/// `ModuleMap`, so a module pulled in by more than one static import isn't
+/// recompiled. Also indexes by V8's per-module identity hash so the
+/// `ResolveModuleCallback` (which only gets handed the referrer `Module`,
+/// not its URL) can find out which module is asking.
+#[derive(Default)]
+pub struct ModuleMap {
+    by_url: HashMap<String, v8::Global<v8::Module>>,
+    url_by_hash: HashMap<i32, String>,
+    /// Completion value of a module that's already been instantiated and
+    /// evaluated as a top-level load (`eval_module`, `load_main_module` or
+    /// `import()`), keyed by URL. A cached URL here short-circuits back to
+    /// this value instead of calling `Module::evaluate` again. Modules
+    /// evaluated only as another module's static import are not recorded.
+    evaluated: HashMap<String, v8::Global<v8::Value>>,
+}
+
+impl ModuleMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, url: &str) -> Option<v8::Global<v8::Module>> {
+        self.by_url.get(url).cloned()
+    }
+
+    pub fn url_of(&self, hash: i32) -> Option<&str> {
+        self.url_by_hash.get(&hash).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, url: String, hash: i32, module: v8::Global<v8::Module>) {
+        self.url_by_hash.insert(hash, url.clone());
+        self.by_url.insert(url, module);
+    }
+
+    /// The cached result of a URL's previous evaluation, if it's already
+    /// been instantiated and evaluated once.
+    pub fn evaluated_value(&self, url: &str) -> Option<v8::Global<v8::Value>> {
+        self.evaluated.get(url).cloned()
+    }
+
+    /// Record that `url`'s module has been evaluated, so later loads of it
+    /// return `value` instead of evaluating it again.
+    pub fn mark_evaluated(&mut self, url: &str, value: v8::Global<v8::Value>) {
+        self.evaluated.insert(url.to_string(), value);
+    }
+}
+
+pub(super) fn default_loader() -> Rc<dyn ModuleLoader> {
+    Rc::new(NoopModuleLoader)
+}
@@ -7,7 +7,17 @@ use v8::Local;
 
 use std::fmt::Write;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
+
+use futures::future::poll_fn;
+use futures::stream::FuturesUnordered;
+use futures::task::AtomicWaker;
+use futures::FutureExt;
+use futures::StreamExt;
 
 use crate::utils;
 use crate::utils::init::initialize_v8;
@@ -16,16 +26,39 @@ use crate::utils::inspect::inspect_v8_value;
 use super::JsState;
 use super::JsStateRef;
 
+mod error;
+mod inspector;
+mod ops;
+mod serialize;
+mod snapshot;
+mod timers;
+
+pub use error::JsError;
+pub use error::JsStackFrame;
+pub use inspector::InspectorServer;
+pub use inspector::JsRuntimeInspector;
+pub use ops::OpCall;
+pub use ops::OpFn;
+pub use ops::OpResult;
+pub use ops::OpTable;
+pub use ops::PendingOpFuture;
+pub use ops::PromiseId;
+pub use snapshot::Snapshot;
+pub use timers::Timers;
+
 #[derive(Debug, PartialEq)]
 pub enum EvalError {
     CompileError,
-    RuntimeError,
+    RuntimeError(JsError),
     ConversionError,
 }
 
 impl std::fmt::Display for EvalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            EvalError::RuntimeError(err) => write!(f, "RuntimeError: {}", err),
+            other => write!(f, "{:?}", other),
+        }
     }
 }
 
@@ -34,6 +67,7 @@ impl std::error::Error for EvalError {}
 pub struct JsRuntime {
     pub(crate) isolate: v8::OwnedIsolate,
     pub(crate) context: Global<Context>,
+    inspector: Option<JsRuntimeInspector>,
 }
 
 extern "C" fn promise_reject_callback(message: v8::PromiseRejectMessage) {
@@ -99,12 +133,57 @@ fn message_from_worker(
 
             println!("[{:?}] console.{}:{}", date, level, output);
         }
+        "message" => {
+            // Round-trip the payload through V8's structured-clone wire
+            // format so object graphs (Maps, typed arrays, nested objects,
+            // cycles) survive the boundary instead of being flattened to a
+            // string, the way `"console"` above does for log arguments.
+            let data = utils::get(scope, message, "data");
+
+            match serialize::serialize_value(scope, data) {
+                Some((bytes, shared_array_buffers)) => {
+                    match serialize::deserialize_value(scope, &bytes, shared_array_buffers) {
+                        Some(value) => println!(
+                            "Worker message: {} ({} bytes)",
+                            inspect_v8_value(value, scope),
+                            bytes.len()
+                        ),
+                        None => println!("Worker message: failed to deserialize payload"),
+                    }
+                }
+                None => println!("Worker message: payload is not structured-cloneable"),
+            }
+        }
         _ => {
             println!("Unknown message kind: {}", kind);
         }
     }
 }
 
+/// `structuredClone(value)`: round-trips `value` through V8's
+/// `ValueSerializer`/`ValueDeserializer`, giving JS the same deep-clone
+/// semantics the `postMessage` boundary uses internally.
+fn structured_clone(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    mut ret: v8::ReturnValue,
+) {
+    let value = args.get(0);
+
+    let (bytes, shared_array_buffers) = match serialize::serialize_value(scope, value) {
+        Some(result) => result,
+        None => {
+            utils::throw_error(scope, "value could not be cloned");
+            return;
+        }
+    };
+
+    match serialize::deserialize_value(scope, &bytes, shared_array_buffers) {
+        Some(clone) => ret.set(clone),
+        None => utils::throw_error(scope, "value could not be cloned"),
+    }
+}
+
 /// Register callback for onMessage
 fn register_message_handler(
     scope: &mut v8::HandleScope,
@@ -149,87 +228,355 @@ fn register_message_handler(
     };
 }
 
+/// `setTimeout(fn, delay)` / `setInterval(fn, delay)`: push a new entry onto
+/// the `JsState` timer heap and hand the id back to JS.
+fn set_timer(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    mut ret: v8::ReturnValue,
+    repeat: bool,
+) {
+    let callback = args.get(0);
+    let callback: Local<v8::Function> = match callback.try_into() {
+        Ok(callback) => callback,
+        Err(_) => {
+            utils::throw_type_error(scope, "Arg 0 is not a function");
+            return;
+        }
+    };
+    let callback = Global::new(scope, callback);
+
+    let delay_ms = args.get(1).integer_value(scope).unwrap_or(0).max(0) as u64;
+
+    let state = scope.get_slot::<JsStateRef>().unwrap();
+    let id = state
+        .borrow_mut()
+        .timers
+        .add(callback, Duration::from_millis(delay_ms), repeat);
+
+    ret.set_uint32(id);
+}
+
+fn set_timeout(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    ret: v8::ReturnValue,
+) {
+    set_timer(scope, args, ret, false);
+}
+
+fn set_interval(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    ret: v8::ReturnValue,
+) {
+    set_timer(scope, args, ret, true);
+}
+
+/// `clearTimeout(id)` / `clearInterval(id)`: both just cancel a heap entry.
+fn clear_timer(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    _ret: v8::ReturnValue,
+) {
+    let id = args.get(0).uint32_value(scope).unwrap_or(0);
+
+    let state = scope.get_slot::<JsStateRef>().unwrap();
+    state.borrow_mut().timers.remove(id);
+}
+
+/// `Deno.core.opcall(opId, arg1, arg2)`: the single JS→Rust bridge. Looks up
+/// `opId` in the runtime's `OpTable` and either returns the op's result
+/// synchronously, throws on a sync error, or (for an async op) enqueues its
+/// future on the event loop and returns the promise it'll settle.
+fn opcall(
+    scope: &mut v8::HandleScope,
+    args: v8::FunctionCallbackArguments,
+    mut ret: v8::ReturnValue,
+) {
+    let op_id = match args.get(0).uint32_value(scope) {
+        Some(id) => id,
+        None => {
+            utils::throw_type_error(scope, "opId must be a uint32");
+            return;
+        }
+    };
+
+    let arg1 = args.get(1);
+    let arg2 = args.get(2);
+
+    let op_fn = {
+        let state = scope.get_slot::<JsStateRef>().unwrap();
+        let state = state.borrow();
+        match state.op_table.get(op_id) {
+            Some(op_fn) => op_fn as *const OpFn,
+            None => {
+                drop(state);
+                utils::throw_error(scope, &format!("Unknown op id {}", op_id));
+                return;
+            }
+        }
+    };
+
+    // Safety: `op_table` only grows (never removes entries) for the
+    // lifetime of the isolate, so the pointer stays valid across the
+    // `scope` reborrow needed to call it while `state`'s borrow is live.
+    let call = unsafe { (*op_fn)(scope, arg1, arg2) };
+
+    match call {
+        OpCall::Sync(Ok(value)) => ret.set(Local::new(scope, value)),
+        OpCall::Sync(Err(value)) => scope.throw_exception(Local::new(scope, value)),
+        OpCall::Async(future) => {
+            let resolver = v8::PromiseResolver::new(scope).unwrap();
+            let promise = resolver.get_promise(scope);
+            let resolver = Global::new(scope, resolver);
+
+            JsRuntime::enqueue_op(scope, resolver, future);
+
+            ret.set(promise.into());
+        }
+    }
+}
+
+/// Native functions [`bootstrap`] installs directly as named globals. The
+/// single source both the install loop below and
+/// [`snapshot::external_references`] read from, so a native function added
+/// here can't be forgotten from the snapshot's external-references table —
+/// which must list every native function pointer baked into the snapshotted
+/// heap, or `SnapshotCreator::create_blob`/deserialization breaks.
+const GLOBAL_FUNCTIONS: &[(&str, v8::FunctionCallback)] = &[
+    ("postMessage", message_from_worker as v8::FunctionCallback),
+    ("onMessage", register_message_handler as v8::FunctionCallback),
+    ("setTimeout", set_timeout as v8::FunctionCallback),
+    ("setInterval", set_interval as v8::FunctionCallback),
+    ("clearTimeout", clear_timer as v8::FunctionCallback),
+    ("clearInterval", clear_timer as v8::FunctionCallback),
+    ("structuredClone", structured_clone as v8::FunctionCallback),
+];
+
+/// Bootstrap scripts run on every fresh isolate: the runtime globals
+/// (`atob`/`btoa`, `console`, `navigator`, events, `fetch`), in the order
+/// they depend on each other.
+const BOOTSTRAP_SCRIPTS: &[&str] = &[
+    include_str!("../runtime/init.js"),
+    include_str!("../runtime/atob.js"),
+    include_str!("../runtime/btoa.js"),
+    include_str!("../runtime/console.js"),
+    include_str!("../runtime/navigator.js"),
+    include_str!("../runtime/events.js"),
+    include_str!("../runtime/fetch/headers.js"),
+    include_str!("../runtime/fetch/response.js"),
+    include_str!("../runtime/fetch/request.js"),
+    include_str!("../runtime/fetch/fetch-event.js"),
+];
+
+/// Installs the `postMessage`/`onMessage` bindings and runs the bootstrap +
+/// message-handler scripts against an already-entered context. Shared by
+/// [`JsRuntime::create_init`] (fresh isolate) and [`JsRuntime::snapshot`]
+/// (`SnapshotCreator` isolate), so a warm start from [`JsRuntime::from_snapshot`]
+/// sees the exact same global surface as a cold one.
+fn bootstrap(scope: &mut ContextScope<HandleScope>) {
+    for script in BOOTSTRAP_SCRIPTS {
+        let code = v8::String::new(scope, script).unwrap();
+        let script = v8::Script::compile(scope, code, None).unwrap();
+        script.run(scope).unwrap();
+    }
+
+    let global = scope.get_current_context().global(scope);
+
+    for (name, callback) in GLOBAL_FUNCTIONS.iter().copied() {
+        let function = v8::FunctionTemplate::new(scope, callback);
+        let function = function.get_function(scope).unwrap();
+        let key = v8::String::new(scope, name).unwrap();
+        global.set(scope, key.into(), function.into());
+    }
+
+    // `Deno.core.opcall` — the generic op dispatch entry point JS-side op
+    // bindings (e.g. the fetch/KV runtime scripts) call into. Nested under
+    // `Deno.core` rather than a top-level global, so it's installed
+    // separately from the loop above, but it's still a native function
+    // pointer baked into the snapshot and must appear in
+    // `snapshot::external_references` like everything in `GLOBAL_FUNCTIONS`.
+    {
+        let core = v8::Object::new(scope);
+
+        let opcall_fn = v8::FunctionTemplate::new(scope, opcall);
+        let opcall_fn = opcall_fn.get_function(scope).unwrap();
+        let key = v8::String::new(scope, "opcall").unwrap();
+        core.set(scope, key.into(), opcall_fn.into());
+
+        let deno = v8::Object::new(scope);
+        let key = v8::String::new(scope, "core").unwrap();
+        deno.set(scope, key.into(), core.into());
+
+        let key = v8::String::new(scope, "Deno").unwrap();
+        global.set(scope, key.into(), deno.into());
+    }
+
+    let code = v8::String::new(scope, include_str!("../runtime/message.js")).unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    script.run(scope).unwrap();
+}
+
 impl JsRuntime {
     /// Create a new context with default extensions
     pub fn create_init() -> Self {
         initialize_v8();
 
-        let mut rt = {
-            let mut isolate = Isolate::new(Default::default());
-
-            isolate.set_capture_stack_trace_for_uncaught_exceptions(false, 0);
-            isolate.set_promise_reject_callback(promise_reject_callback);
-            isolate.add_message_listener(message_callback);
+        let mut isolate = Isolate::new(Default::default());
 
-            let context = {
-                let scope = &mut HandleScope::new(&mut isolate);
+        isolate.set_capture_stack_trace_for_uncaught_exceptions(true, 10);
+        isolate.set_promise_reject_callback(promise_reject_callback);
+        isolate.add_message_listener(message_callback);
 
-                let context = Context::new(scope);
+        let context = {
+            let scope = &mut HandleScope::new(&mut isolate);
 
-                let scope = &mut ContextScope::new(scope, context);
+            let context = Context::new(scope);
 
-                scope.set_slot(Rc::new(RefCell::new(JsState {
-                    handler: None,
-                    // timers: Timers::new(),
-                })));
+            let scope = &mut ContextScope::new(scope, context);
 
-                let context = Global::new(scope, context);
+            scope.set_slot(Rc::new(RefCell::new(JsState {
+                handler: None,
+                timers: Timers::new(),
+                pending_ops: FuturesUnordered::new(),
+                promises: HashMap::new(),
+                waker: AtomicWaker::new(),
+                op_table: OpTable::new(),
+            })));
 
-                context
-            };
+            bootstrap(scope);
 
-            JsRuntime { isolate, context }
+            Global::new(scope, context)
         };
 
-        rt.eval(include_str!("../runtime/init.js")).unwrap();
-        rt.eval(include_str!("../runtime/atob.js")).unwrap();
-        rt.eval(include_str!("../runtime/btoa.js")).unwrap();
-        rt.eval(include_str!("../runtime/console.js")).unwrap();
-        rt.eval(include_str!("../runtime/navigator.js")).unwrap();
-        rt.eval(include_str!("../runtime/events.js")).unwrap();
-        rt.eval(include_str!("../runtime/fetch/headers.js"))
-            .unwrap();
-        rt.eval(include_str!("../runtime/fetch/response.js"))
-            .unwrap();
-        rt.eval(include_str!("../runtime/fetch/request.js"))
-            .unwrap();
-        rt.eval(include_str!("../runtime/fetch/fetch-event.js"))
-            .unwrap();
+        JsRuntime {
+            isolate,
+            context,
+            inspector: None,
+        }
+    }
+
+    /// Like [`Self::create_init`], but attaches a Chrome DevTools inspector
+    /// to the context.
+    ///
+    /// `wait_for_debugger` is recorded but not yet wired to anything: there
+    /// is no real CDP transport behind the returned inspector's sessions yet
+    /// (see the doc comments on `inspector::InspectorClient` and
+    /// `inspector::NullChannel`), so execution does not actually pause for a
+    /// front-end to attach. Treat this as the skeleton for DevTools support,
+    /// not the finished feature.
+    pub fn create_init_with_inspector(wait_for_debugger: bool) -> Self {
+        let mut rt = Self::create_init();
 
-        // TODO: Snapshot here
+        let isolate = &mut rt.isolate;
+        let context = rt.context.clone();
+
+        rt.inspector = Some(JsRuntimeInspector::new(isolate, context, wait_for_debugger));
+
+        rt
+    }
+
+    /// The runtime's inspector, if it was created with
+    /// [`Self::create_init_with_inspector`].
+    pub fn inspector(&mut self) -> Option<&mut JsRuntimeInspector> {
+        self.inspector.as_mut()
+    }
+
+    /// Run the bootstrap scripts in a `SnapshotCreator` isolate and serialize
+    /// the resulting heap into a startup blob. Loading that blob back via
+    /// [`JsRuntime::from_snapshot`] skips re-compiling `init.js` and friends,
+    /// which dominates the cost of spinning up a fresh isolate.
+    pub fn snapshot() -> Snapshot {
+        initialize_v8();
+
+        let external_refs = snapshot::external_references();
+        let mut creator = v8::SnapshotCreator::new(Some(external_refs));
 
-        // Set postMessage handler
         {
-            let scope = &mut HandleScope::new(&mut rt.isolate);
-            let context = Local::new(scope, &rt.context);
-            let global = context.global(scope);
+            let isolate = unsafe { creator.get_owned_isolate() };
+            let mut isolate = isolate;
+            let scope = &mut HandleScope::new(&mut isolate);
+
+            let context = Context::new(scope);
             let scope = &mut ContextScope::new(scope, context);
 
-            let post_message = v8::FunctionTemplate::new(scope, message_from_worker);
-            let post_message = post_message.get_function(scope).unwrap();
+            scope.set_slot(Rc::new(RefCell::new(JsState {
+                handler: None,
+                timers: Timers::new(),
+                pending_ops: FuturesUnordered::new(),
+                promises: HashMap::new(),
+                waker: AtomicWaker::new(),
+                op_table: OpTable::new(),
+            })));
+
+            bootstrap(scope);
+
+            scope.set_default_context(context);
 
-            let name = v8::String::new(scope, "postMessage").unwrap();
-            global.set(scope, name.into(), post_message.into());
+            std::mem::forget(isolate);
         }
 
-        // Set onMessage handler
-        {
-            let scope = &mut HandleScope::new(&mut rt.isolate);
-            let context = Local::new(scope, &rt.context);
-            let global = context.global(scope);
+        let blob = creator
+            .create_blob(v8::FunctionCodeHandling::Keep)
+            .expect("failed to create snapshot blob");
+
+        Snapshot::JustCreated(blob)
+    }
+
+    /// Create a new context from a previously-created startup snapshot,
+    /// skipping script compilation for everything the snapshot already ran.
+    pub fn from_snapshot(snapshot: &'static [u8]) -> Self {
+        initialize_v8();
+
+        let external_refs = snapshot::external_references();
+        let params = v8::CreateParams::default()
+            .snapshot_blob(snapshot.to_vec())
+            .external_references(external_refs.as_ref());
+
+        let mut isolate = Isolate::new(params);
+
+        isolate.set_capture_stack_trace_for_uncaught_exceptions(true, 10);
+        isolate.set_promise_reject_callback(promise_reject_callback);
+        isolate.add_message_listener(message_callback);
+
+        let context = {
+            let scope = &mut HandleScope::new(&mut isolate);
+
+            // The default context and its globals (including `postMessage`/
+            // `onMessage`) come straight from the snapshot.
+            let context = Context::new(scope);
+
             let scope = &mut ContextScope::new(scope, context);
 
-            let on_message = v8::FunctionTemplate::new(scope, register_message_handler);
-            let on_message = on_message.get_function(scope).unwrap();
+            scope.set_slot(Rc::new(RefCell::new(JsState {
+                handler: None,
+                timers: Timers::new(),
+                pending_ops: FuturesUnordered::new(),
+                promises: HashMap::new(),
+                waker: AtomicWaker::new(),
+                op_table: OpTable::new(),
+            })));
 
-            let name = v8::String::new(scope, "onMessage").unwrap();
-            global.set(scope, name.into(), on_message.into());
+            Global::new(scope, context)
+        };
+
+        JsRuntime {
+            isolate,
+            context,
+            inspector: None,
         }
+    }
 
-        // Runtime message handler
-        rt.eval(include_str!("../runtime/message.js")).unwrap();
+    /// Register a native op callable from JS as `Deno.core.opcall(id, ...)`.
+    /// Returns the stable id assigned to it.
+    pub fn register_op(&mut self, name: &'static str, op_fn: OpFn) -> u32 {
+        let scope = &mut HandleScope::new(&mut self.isolate);
+        let context = Local::new(scope, &self.context);
+        let scope = &mut ContextScope::new(scope, context);
 
-        rt
+        let state = scope.get_slot::<JsStateRef>().unwrap();
+        state.borrow_mut().op_table.register(name, op_fn)
     }
 
     /// Evaluate a script
@@ -238,22 +585,32 @@ impl JsRuntime {
 
         let context = Local::new(scope, &self.context);
         let scope = &mut ContextScope::new(scope, context);
+        let scope = &mut v8::TryCatch::new(scope);
 
         let code = v8::String::new(scope, script).ok_or(EvalError::CompileError)?;
         let script = v8::Script::compile(scope, code, None).ok_or(EvalError::CompileError)?;
 
         // Run script
-        let result = script.run(scope).ok_or(EvalError::RuntimeError)?;
+        let result = script.run(scope).ok_or_else(|| {
+            EvalError::RuntimeError(JsError::from_try_catch(scope).unwrap_or(JsError {
+                message: "unknown error".to_string(),
+                stack: None,
+                frames: Vec::new(),
+            }))
+        })?;
 
         let result = result.to_string(scope).ok_or(EvalError::ConversionError)?;
 
         Ok(result.to_rust_string_lossy(scope))
     }
 
+    /// Call the registered `onMessage` handler with `event`. Returns the
+    /// handler's result, `Ok(None)` if no handler is registered, or the
+    /// captured [`JsError`] if the handler threw.
     pub fn send_message<E: super::message::RuntimeMessage>(
         &mut self,
         event: &mut E,
-    ) -> Option<Local<v8::Value>> {
+    ) -> Result<Option<Local<v8::Value>>, JsError> {
         let scope = &mut HandleScope::new(&mut self.isolate);
         let context = Local::new(scope, &self.context);
 
@@ -261,6 +618,7 @@ impl JsRuntime {
 
         let result = {
             let scope = &mut ContextScope::new(scope, context);
+            let scope = &mut v8::TryCatch::new(scope);
 
             // Get handler - State must be dropped before the handler is called
             let handler = {
@@ -270,7 +628,7 @@ impl JsRuntime {
                     Some(handler) => handler,
                     None => {
                         println!("No handler registered");
-                        return None;
+                        return Ok(None);
                     }
                 }
             };
@@ -282,34 +640,286 @@ impl JsRuntime {
             let event = event.to_value(scope);
 
             // Call handler
-            let result = handler.call(scope, undefined, &[event]);
-
-            println!("Event result: {:?}", result);
-
-            result
+            match handler.call(scope, undefined, &[event]) {
+                Some(result) => Ok(Some(result)),
+                None => Err(JsError::from_try_catch(scope).unwrap_or(JsError {
+                    message: "handler threw without an exception".to_string(),
+                    stack: None,
+                    frames: Vec::new(),
+                })),
+            }
         };
 
         result
     }
 
-    pub async fn run_event_loop<'a>(&mut self) {
-        let scope = &mut HandleScope::new(&mut self.isolate);
-        let context = Local::new(scope, &self.context);
-        let scope = &mut ContextScope::new(scope, context);
+    /// Register an async op's future so the event loop resolves `promise`
+    /// with its result once it completes. Assigns the op's `promise_id`
+    /// here — before the future is ever polled — and stamps it onto the
+    /// future's eventual [`OpResult`], since the op itself has no way to
+    /// know in advance what id it will be given.
+    pub(crate) fn enqueue_op(
+        scope: &mut v8::HandleScope,
+        promise: Global<v8::PromiseResolver>,
+        future: ops::RawOpFuture,
+    ) -> PromiseId {
+        let state = scope.get_slot::<JsStateRef>().unwrap();
+        let mut state = state.borrow_mut();
+
+        let id = state.op_table.next_promise_id();
+        state.promises.insert(id, promise);
+        state
+            .pending_ops
+            .push(Box::pin(future.map(move |value| OpResult { promise_id: id, value })));
+        state.waker.wake();
+
+        id
+    }
+
+    /// Drive the runtime's timers and pending ops to completion, like
+    /// deno_core's event loop: each turn performs a microtask checkpoint,
+    /// resolves any pending op promises whose futures completed, fires due
+    /// timers, then parks until the soonest timer deadline or a new pending
+    /// op wakes the loop. Exits once both the timer heap and pending-op set
+    /// are empty, so `await`-ing `fetch`/`send_message` handlers resolve
+    /// before control returns to the async Rust caller.
+    pub async fn run_event_loop(&mut self) {
+        poll_fn(|cx| {
+            let scope = &mut HandleScope::new(&mut self.isolate);
+            let context = Local::new(scope, &self.context);
+            let scope = &mut ContextScope::new(scope, context);
 
-        loop {
-            // tokio::macros::support::poll_fn(|cx| Self::poll_timers(cx, scope)).await;
+            let state_ref = scope.get_slot::<JsStateRef>().expect("No state found").clone();
+
+            // Register before draining/firing anything below: a timer
+            // callback (or a `.then()` continuation run by a microtask
+            // checkpoint) can synchronously enqueue a new op, whose
+            // `enqueue_op` calls `state.waker.wake()` eagerly. If that
+            // happens after registration there's nothing to lose the wake;
+            // registering any later (e.g. only right before returning
+            // `Pending`) would drop a wake that already fired this tick,
+            // hanging the loop forever since the new future wasn't present
+            // in `pending_ops` when `poll_next_unpin` ran above either.
+            state_ref.borrow().waker.register(cx.waker());
 
             scope.perform_microtask_checkpoint();
 
-            // let state = scope.get_slot::<super::JsStateRef>().expect("No state found");
+            // Resolve/reject promises for ops that finished since the last turn.
+            loop {
+                let next_result = {
+                    let mut state = state_ref.borrow_mut();
+                    match state.pending_ops.is_empty() {
+                        true => None,
+                        false => match state.pending_ops.poll_next_unpin(cx) {
+                            Poll::Ready(Some(result)) => Some(result),
+                            _ => None,
+                        },
+                    }
+                };
+
+                let Some(result) = next_result else { break };
+
+                let resolver = state_ref.borrow_mut().promises.remove(&result.promise_id);
+                if let Some(resolver) = resolver {
+                    let resolver = Local::new(scope, resolver);
+                    match result.value {
+                        Ok(value) => {
+                            resolver.resolve(scope, Local::new(scope, value));
+                        }
+                        Err(value) => {
+                            resolver.reject(scope, Local::new(scope, value));
+                        }
+                    };
+                }
+            }
 
-            // // Check if we are done
-            // if state.borrow().timers.is_empty() {
-            //     break;
-            // }
+            // Fire any timers whose deadline has passed.
+            let ready = state_ref.borrow_mut().timers.drain_ready(Instant::now());
+            let undefined = v8::undefined(scope).into();
+            for callback in ready {
+                let callback = Local::new(scope, callback);
+                callback.call(scope, undefined, &[]);
+            }
 
-            break;
-        }
+            scope.perform_microtask_checkpoint();
+
+            let state = state_ref.borrow();
+            if state.timers.is_empty() && state.pending_ops.is_empty() {
+                return Poll::Ready(());
+            }
+
+            // `cx.waker()` was already registered with `state.waker` above,
+            // before anything could call `wake()` this tick; pending-op
+            // completion separately wakes via `cx`'s waker through
+            // `poll_next_unpin`. Here we just additionally wake for the next
+            // timer deadline, since nothing else will.
+            if let Some(deadline) = state.timers.next_deadline() {
+                let waker = cx.waker().clone();
+                let delay = deadline.saturating_duration_since(Instant::now());
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    waker.wake();
+                });
+            }
+
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `setTimeout` should fire once its delay elapses; `clearTimeout`
+    /// should stop a timer that hasn't fired yet from ever running.
+    #[tokio::test]
+    async fn timers_fire_and_clear() {
+        let mut rt = JsRuntime::create_init();
+
+        rt.eval(
+            "globalThis.fired = [];
+             setTimeout(() => fired.push('a'), 0);
+             globalThis.cleared = setTimeout(() => fired.push('b'), 50);
+             clearTimeout(cleared);",
+        )
+        .unwrap();
+
+        rt.run_event_loop().await;
+
+        assert_eq!(rt.eval("JSON.stringify(fired)").unwrap(), "[\"a\"]");
+    }
+
+    /// `structuredClone` should deep-clone its argument rather than just
+    /// returning the same reference.
+    #[test]
+    fn structured_clone_round_trips_nested_objects() {
+        let mut rt = JsRuntime::create_init();
+
+        let result = rt
+            .eval(
+                "const original = { a: 1, nested: { b: [1, 2, 3] } };
+                 const clone = structuredClone(original);
+                 JSON.stringify(clone) === JSON.stringify(original) && clone !== original;",
+            )
+            .unwrap();
+
+        assert_eq!(result, "true");
+    }
+
+    /// Unlike a plain `ArrayBuffer`, a `SharedArrayBuffer` given to
+    /// `structuredClone` must come back sharing the same backing store, not
+    /// a copy: a write through one view should be visible through the
+    /// other's.
+    #[test]
+    fn structured_clone_transfers_shared_array_buffers_by_reference() {
+        let mut rt = JsRuntime::create_init();
+
+        let result = rt
+            .eval(
+                "const original = new SharedArrayBuffer(4);
+                 const view = new Int32Array(original);
+                 const clone = structuredClone(original);
+                 const cloneView = new Int32Array(clone);
+                 view[0] = 42;
+                 cloneView[0] === 42;",
+            )
+            .unwrap();
+
+        assert_eq!(result, "true");
+    }
+
+    /// A registered async op's future should resolve the promise
+    /// `Deno.core.opcall` handed back to JS once the event loop drives it
+    /// to completion, and two ops in flight at once shouldn't collide on
+    /// the same `promise_id`.
+    #[tokio::test]
+    async fn async_op_resolves_its_promise() {
+        let mut rt = JsRuntime::create_init();
+
+        let op_id = rt.register_op(
+            "op_double",
+            Box::new(|scope, arg, _arg2| {
+                let n = arg.integer_value(scope).unwrap_or(0);
+                let result = v8::Integer::new(scope, (n * 2) as i32);
+                let result = Global::new(scope, Local::<v8::Value>::from(result));
+
+                OpCall::Async(Box::pin(async move { Ok(result) }))
+            }),
+        );
+
+        rt.eval(&format!(
+            "globalThis.results = [];
+             Deno.core.opcall({op_id}, 1).then((v) => results.push(v));
+             Deno.core.opcall({op_id}, 20).then((v) => results.push(v));"
+        ))
+        .unwrap();
+
+        rt.run_event_loop().await;
+
+        assert_eq!(rt.eval("JSON.stringify(results)").unwrap(), "[2,40]");
+    }
+
+    /// A context started from a freshly-created snapshot should see the
+    /// exact same bootstrapped globals as a cold `create_init`, including
+    /// the natives (`opcall`, timers, `structuredClone`) added after
+    /// `Snapshot` itself was introduced.
+    #[test]
+    fn snapshot_then_from_snapshot_preserves_bootstrap() {
+        let snapshot = JsRuntime::snapshot();
+        let bytes: &'static [u8] = Box::leak(snapshot.as_bytes().to_vec().into_boxed_slice());
+
+        let mut rt = JsRuntime::from_snapshot(bytes);
+
+        assert_eq!(rt.eval("typeof postMessage").unwrap(), "function");
+        assert_eq!(rt.eval("typeof Deno.core.opcall").unwrap(), "function");
+        assert_eq!(rt.eval("typeof setTimeout").unwrap(), "function");
+        assert_eq!(rt.eval("typeof structuredClone").unwrap(), "function");
+    }
+
+    /// Regression test for a lost-wakeup hang: a timer firing on the last
+    /// tick before the timer heap goes empty synchronously enqueues a new
+    /// async op (the ordinary `setTimeout(() => doAsyncOp())` pattern).
+    /// `enqueue_op`'s `wake()` used to be dropped because `state.waker`
+    /// wasn't registered with this tick's `cx.waker()` until after timers
+    /// fired, and with no timer deadline left to fall back on, nothing would
+    /// ever poll the new future again — hanging `run_event_loop` forever.
+    #[tokio::test]
+    async fn op_enqueued_from_a_firing_timer_still_resolves() {
+        let mut rt = JsRuntime::create_init();
+
+        let op_id = rt.register_op(
+            "op_double",
+            Box::new(|scope, arg, _arg2| {
+                let n = arg.integer_value(scope).unwrap_or(0);
+                let result = v8::Integer::new(scope, (n * 2) as i32);
+                let result = Global::new(scope, Local::<v8::Value>::from(result));
+
+                OpCall::Async(Box::pin(async move { Ok(result) }))
+            }),
+        );
+
+        rt.eval(&format!(
+            "globalThis.result = undefined;
+             setTimeout(() => {{
+                 Deno.core.opcall({op_id}, 21).then((v) => {{ result = v; }});
+             }}, 0);"
+        ))
+        .unwrap();
+
+        rt.run_event_loop().await;
+
+        assert_eq!(rt.eval("result").unwrap(), "42");
+    }
+
+    /// Regression test for the `NullChannel` stub: connecting a session used
+    /// to panic immediately (`ChannelImpl::base`/`base_mut` were
+    /// `unimplemented!()` with no backing field), so any inspector use at
+    /// all, real transport or not, was broken.
+    #[test]
+    fn inspector_session_connects_without_panicking() {
+        let mut rt = JsRuntime::create_init_with_inspector(false);
+        let _session = rt.inspector().unwrap().connect();
     }
 }
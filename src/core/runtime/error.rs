@@ -0,0 +1,78 @@
+/// One frame of a parsed V8 stack trace, borrowed from deno_core's error
+/// model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsStackFrame {
+    pub file_name: Option<String>,
+    pub line_number: Option<u32>,
+    pub column_number: Option<u32>,
+    pub function_name: Option<String>,
+}
+
+impl JsStackFrame {
+    fn from_v8(scope: &mut v8::HandleScope, frame: v8::Local<v8::StackFrame>) -> Self {
+        let file_name = frame.get_script_name(scope).map(|s| s.to_rust_string_lossy(scope));
+        let function_name = frame
+            .get_function_name(scope)
+            .map(|s| s.to_rust_string_lossy(scope));
+
+        JsStackFrame {
+            file_name,
+            line_number: Some(frame.get_line_number() as u32),
+            column_number: Some(frame.get_column() as u32),
+            function_name,
+        }
+    }
+}
+
+/// A structured, uncaught JS exception: the error's message, its `.stack`
+/// string (if V8 produced one), and the parsed call stack behind it. Carried
+/// through [`super::EvalError::RuntimeError`] so callers get actionable
+/// diagnostics instead of a bare variant or a panic.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsError {
+    pub message: String,
+    pub stack: Option<String>,
+    pub frames: Vec<JsStackFrame>,
+}
+
+impl JsError {
+    /// Build a `JsError` from a live `TryCatch` that has an exception
+    /// pending. Returns `None` if the catch isn't actually holding one.
+    pub fn from_try_catch<'s>(
+        scope: &mut v8::TryCatch<'s, v8::HandleScope<'s>>,
+    ) -> Option<Self> {
+        let exception = scope.exception()?;
+
+        let message = exception.to_rust_string_lossy(scope);
+
+        let stack = scope
+            .stack_trace()
+            .map(|stack| stack.to_rust_string_lossy(scope));
+
+        let frames = scope
+            .message()
+            .and_then(|message| message.get_stack_trace(scope))
+            .map(|trace| {
+                (0..trace.get_frame_count())
+                    .filter_map(|i| trace.get_frame(scope, i))
+                    .map(|frame| JsStackFrame::from_v8(scope, frame))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(JsError {
+            message,
+            stack,
+            frames,
+        })
+    }
+}
+
+impl std::fmt::Display for JsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.stack {
+            Some(stack) => write!(f, "{}", stack),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use v8::inspector::ChannelImpl;
+use v8::inspector::V8Inspector;
+use v8::inspector::V8InspectorClientImpl;
+use v8::inspector::V8InspectorSession;
+
+const CONTEXT_GROUP_ID: i32 = 1;
+
+/// Host side of the inspector protocol's client hooks: V8 calls into this to
+/// pause/resume the isolate when a breakpoint is hit. Modeled on deno_core's
+/// `V8InspectorClientImpl`.
+///
+/// **Stub**: pausing isn't functional yet. Nothing in this tree wires up a
+/// real CDP transport ([`NullChannel`] drops every message and
+/// [`InspectorServer`] is an address holder with no listener), so there's no
+/// way for a front-end to ever call `quit_message_loop_on_pause` and resume
+/// a real pause. `run_message_loop_on_pause` therefore returns immediately
+/// instead of blocking, and `wait_for_debugger` currently has no observable
+/// effect beyond that no-op. Land a real transport before relying on either.
+pub struct InspectorClient {
+    base: v8::inspector::V8InspectorClientBase,
+    wait_for_debugger: bool,
+    paused: Rc<RefCell<bool>>,
+}
+
+impl InspectorClient {
+    fn new(wait_for_debugger: bool) -> Box<Self> {
+        Box::new(Self {
+            base: v8::inspector::V8InspectorClientBase::new::<Self>(),
+            wait_for_debugger,
+            paused: Rc::new(RefCell::new(false)),
+        })
+    }
+}
+
+impl V8InspectorClientImpl for InspectorClient {
+    fn base(&self) -> &v8::inspector::V8InspectorClientBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut v8::inspector::V8InspectorClientBase {
+        &mut self.base
+    }
+
+    /// Called by V8 when a breakpoint is hit (or the "wait for debugger on
+    /// first statement" flag above would pause execution). There's no real
+    /// CDP transport wired up yet (see [`NullChannel`]/[`InspectorServer`]),
+    /// so nothing could ever call `quit_message_loop_on_pause` to resume a
+    /// real pause — spinning here would just hang the isolate forever. Until
+    /// a transport exists this is a documented no-op rather than a fake
+    /// pause: it sets `paused` and clears it again before returning, so
+    /// `paused` is never observed as `true`.
+    fn run_message_loop_on_pause(&mut self, _context_group_id: i32) {
+        *self.paused.borrow_mut() = true;
+        *self.paused.borrow_mut() = false;
+    }
+
+    fn quit_message_loop_on_pause(&mut self) {
+        *self.paused.borrow_mut() = false;
+    }
+
+    /// V8 calls this once at context creation to give a "wait for debugger"
+    /// client its chance to pause. Not invoked from anywhere in this tree
+    /// yet — [`JsRuntimeInspector::new`] never calls it — so
+    /// `wait_for_debugger` currently has no effect regardless of this
+    /// method's own (also-stubbed) behavior; see the type-level doc comment.
+    fn run_if_waiting_for_debugger(&mut self, _context_group_id: i32) {
+        if self.wait_for_debugger {
+            self.run_message_loop_on_pause(CONTEXT_GROUP_ID);
+        }
+    }
+}
+
+/// Owns the `V8Inspector` for a context (a session can't outlive it) and
+/// hands out `V8InspectorSession`s that relay CDP (`Inspector.*`/
+/// `Debugger.*`/`Runtime.*`) messages.
+///
+/// **Stub**: [`Self::connect`]'s sessions are wired to [`NullChannel`], which
+/// drops every outgoing message, so there is no real DevTools front-end
+/// support yet — see the doc comments on [`InspectorClient`] and
+/// [`NullChannel`] for what's missing.
+pub struct JsRuntimeInspector {
+    v8_inspector: Rc<RefCell<v8::UniqueRef<V8Inspector>>>,
+    client: Box<InspectorClient>,
+}
+
+impl JsRuntimeInspector {
+    /// Create the inspector and attach it to `context`. `wait_for_debugger`
+    /// is recorded on the client but, absent a real CDP transport, has no
+    /// observable effect yet — see [`InspectorClient`]'s doc comment.
+    pub fn new(
+        isolate: &mut v8::Isolate,
+        context: v8::Global<v8::Context>,
+        wait_for_debugger: bool,
+    ) -> Self {
+        let mut client = InspectorClient::new(wait_for_debugger);
+
+        let v8_inspector = V8Inspector::create(isolate, &mut *client);
+
+        {
+            let scope = &mut v8::HandleScope::new(isolate);
+            let context = v8::Local::new(scope, context);
+            let context_name = v8::inspector::StringView::from(&b"openworkers"[..]);
+
+            v8_inspector.context_created(context, CONTEXT_GROUP_ID, context_name);
+        }
+
+        JsRuntimeInspector {
+            v8_inspector: Rc::new(RefCell::new(v8_inspector)),
+            client,
+        }
+    }
+
+    /// Open a new CDP session (one per connected DevTools front-end). The
+    /// session is wired to a [`NullChannel`], so outgoing CDP messages are
+    /// dropped until a real transport replaces it.
+    pub fn connect(&self) -> v8::UniqueRef<V8InspectorSession> {
+        self.v8_inspector.borrow_mut().connect(
+            CONTEXT_GROUP_ID,
+            NullChannel::new(),
+            v8::inspector::StringView::empty(),
+            v8::inspector::V8InspectorClientTrustLevel::FullyTrusted,
+        )
+    }
+}
+
+/// A `Channel` that drops every message sent to the front-end.
+///
+/// **Stub**: this is a placeholder so [`JsRuntimeInspector::connect`] can
+/// hand back a working `V8InspectorSession` without a real transport. It
+/// must be replaced with one that actually writes to a connected front-end
+/// (e.g. a WebSocket write half owned per connection) before this counts as
+/// working DevTools support.
+struct NullChannel {
+    base: v8::inspector::ChannelBase,
+}
+
+impl NullChannel {
+    fn new() -> Box<Self> {
+        Box::new(Self {
+            base: v8::inspector::ChannelBase::new::<Self>(),
+        })
+    }
+}
+
+impl ChannelImpl for NullChannel {
+    fn base(&self) -> &v8::inspector::ChannelBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut v8::inspector::ChannelBase {
+        &mut self.base
+    }
+
+    fn send_response(&mut self, _call_id: i32, _message: v8::UniquePtr<v8::inspector::StringBuffer>) {}
+
+    fn send_notification(&mut self, _message: v8::UniquePtr<v8::inspector::StringBuffer>) {}
+
+    fn flush_protocol_notifications(&mut self) {}
+}
+
+/// The address a Chrome DevTools front-end would connect to. It only holds
+/// `addr`: it opens no listener, accepts no WebSocket connections and relays
+/// no CDP frames to or from a [`JsRuntimeInspector`] session.
+pub struct InspectorServer {
+    pub addr: std::net::SocketAddr,
+}
+
+impl InspectorServer {
+    pub fn new(addr: std::net::SocketAddr) -> Self {
+        InspectorServer { addr }
+    }
+}
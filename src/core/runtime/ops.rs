@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Id the runtime uses to correlate an async op's future with the resolver
+/// of the promise `Deno.core.opcall` returned to JS; assigned by
+/// [`super::JsRuntime::enqueue_op`] and threaded through the event loop
+/// until the op's future resolves. JS itself never sees it.
+pub type PromiseId = u32;
+
+/// What an async op resolves (or rejects) with. An op author can build this
+/// but can never know its own `promise_id` up front — that id is only
+/// assigned once [`super::JsRuntime::enqueue_op`] registers the future on
+/// the event loop — so it isn't part of this type.
+pub type OpOutput = Result<v8::Global<v8::Value>, v8::Global<v8::Value>>;
+
+/// Outcome of a completed async op, ready to be applied to the JS promise
+/// identified by `promise_id` once the event loop gets a scope to do so.
+/// Built by [`super::JsRuntime::enqueue_op`], which pairs an op's
+/// [`OpOutput`] future with the id it was assigned.
+pub struct OpResult {
+    pub promise_id: PromiseId,
+    pub value: OpOutput,
+}
+
+/// A single in-flight async op's own future, as returned by an [`OpFn`].
+/// [`super::JsRuntime::enqueue_op`] wraps this into a [`PendingOpFuture`]
+/// once it has assigned the op a `promise_id`.
+pub type RawOpFuture = Pin<Box<dyn Future<Output = OpOutput>>>;
+
+/// A [`RawOpFuture`] paired with the `promise_id` it was enqueued under.
+/// Stored in [`super::JsState`]'s `FuturesUnordered` and polled by
+/// [`super::JsRuntime::run_event_loop`] alongside the timer heap.
+pub type PendingOpFuture = Pin<Box<dyn Future<Output = OpResult>>>;
+
+/// What a registered op does when `Deno.core.opcall` invokes it: resolve
+/// immediately with a value (or a thrown exception), or hand back a future
+/// that the event loop polls to completion.
+pub enum OpCall {
+    Sync(OpOutput),
+    Async(RawOpFuture),
+}
+
+/// A registered op, analogous to deno_core's `OpDecl`: takes the two
+/// positional args `Deno.core.opcall` was invoked with and produces an
+/// [`OpCall`].
+pub type OpFn = Box<
+    dyn Fn(&mut v8::HandleScope, v8::Local<v8::Value>, v8::Local<v8::Value>) -> OpCall,
+>;
+
+/// Maps stable numeric op ids to their handlers, like deno_core's `OpTable`.
+/// Ids are assigned in registration order so JS-side generated bindings
+/// (`core.ops.op_fetch = 3`, etc.) stay stable across a process's lifetime.
+#[derive(Default)]
+pub struct OpTable {
+    fns: Vec<OpFn>,
+    ids_by_name: HashMap<&'static str, u32>,
+    next_promise_id: PromiseId,
+}
+
+impl OpTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new op and return the id JS should use to call it.
+    pub fn register(&mut self, name: &'static str, op_fn: OpFn) -> u32 {
+        let id = self.fns.len() as u32;
+        self.fns.push(op_fn);
+        self.ids_by_name.insert(name, id);
+        id
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<u32> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    pub fn get(&self, id: u32) -> Option<&OpFn> {
+        self.fns.get(id as usize)
+    }
+
+    /// Allocate the next `promise_id` for an async op. Monotonically
+    /// increasing (like `Timers`'s own id counter) so a still-pending op's
+    /// id is never reused once an earlier promise resolves and its
+    /// resolver is removed from the promise map — reusing ids (e.g. via
+    /// `promises.len()`) lets two in-flight futures collide on the same id
+    /// and silently drop one resolver forever.
+    pub fn next_promise_id(&mut self) -> PromiseId {
+        let id = self.next_promise_id;
+        self.next_promise_id = self.next_promise_id.wrapping_add(1);
+        id
+    }
+}
@@ -0,0 +1,108 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use v8::ValueDeserializerHelper;
+use v8::ValueSerializerHelper;
+
+/// `SharedArrayBuffer` backing stores encountered while serializing a value,
+/// indexed by the id [`Serializer::get_shared_array_buffer_id`] assigned
+/// each one. A `deserialize_value` call that's meant to materialize the same
+/// `SharedArrayBuffer`s (same backing memory, not a copy) needs this same
+/// list, keyed by the same ids — so a serialize/deserialize pair transferring
+/// any `SharedArrayBuffer`s must share one.
+pub type SharedArrayBuffers = Rc<RefCell<Vec<v8::SharedRef<v8::BackingStore>>>>;
+
+/// Delegate for `v8::ValueSerializer`. `SharedArrayBuffer`s are transferred
+/// by reference, sharing their backing store with whatever deserializes the
+/// payload (see [`SharedArrayBuffers`]) rather than being copied. We don't
+/// support any host object types (e.g. a `WasmModuleObject` wrapper) yet, so
+/// `write_host_object` is left at its trait default, which reports the value
+/// as unclonable — matching deno_core's baseline `ValueSerializerImpl` for
+/// the hooks it doesn't implement either.
+struct Serializer {
+    shared_array_buffers: SharedArrayBuffers,
+}
+
+impl v8::ValueSerializerImpl for Serializer {
+    fn throw_data_clone_error<'s>(&mut self, scope: &mut v8::HandleScope<'s>, message: v8::Local<'s, v8::String>) {
+        let error = v8::Exception::type_error(scope, message);
+        scope.throw_exception(error);
+    }
+
+    fn get_shared_array_buffer_id<'s>(
+        &mut self,
+        _scope: &mut v8::HandleScope<'s>,
+        shared_array_buffer: v8::Local<'s, v8::SharedArrayBuffer>,
+    ) -> Option<u32> {
+        let mut buffers = self.shared_array_buffers.borrow_mut();
+        let id = buffers.len() as u32;
+        buffers.push(shared_array_buffer.get_backing_store());
+        Some(id)
+    }
+}
+
+/// Delegate for `v8::ValueDeserializer`. Resolves the `SharedArrayBuffer`
+/// ids [`Serializer::get_shared_array_buffer_id`] assigned back to
+/// `SharedArrayBuffer`s over the same backing store; no other host-object
+/// hooks are needed until `Serializer` above starts emitting any.
+struct Deserializer {
+    shared_array_buffers: SharedArrayBuffers,
+}
+
+impl v8::ValueDeserializerImpl for Deserializer {
+    fn get_shared_array_buffer_from_id<'s>(
+        &mut self,
+        scope: &mut v8::HandleScope<'s>,
+        transfer_id: u32,
+    ) -> Option<v8::Local<'s, v8::SharedArrayBuffer>> {
+        let backing_store = self.shared_array_buffers.borrow().get(transfer_id as usize)?.clone();
+
+        Some(v8::SharedArrayBuffer::with_backing_store(scope, &backing_store))
+    }
+}
+
+/// Serialize a JS value into V8's structured-clone wire format, as used by
+/// both `structuredClone` and the `postMessage`/`onMessage` boundary so
+/// object graphs (Maps, typed arrays, nested objects, cycles) survive
+/// intact instead of being flattened to strings. Any `SharedArrayBuffer`s
+/// reachable from `value` are recorded in the returned [`SharedArrayBuffers`]
+/// list rather than copied into the byte payload; pass that same list to
+/// [`deserialize_value`] to hand them back sharing the same backing memory.
+pub fn serialize_value(
+    scope: &mut v8::HandleScope,
+    value: v8::Local<v8::Value>,
+) -> Option<(Vec<u8>, SharedArrayBuffers)> {
+    let shared_array_buffers: SharedArrayBuffers = Rc::new(RefCell::new(Vec::new()));
+
+    let mut serializer = v8::ValueSerializer::new(
+        scope,
+        Box::new(Serializer {
+            shared_array_buffers: shared_array_buffers.clone(),
+        }),
+    );
+    serializer.write_header();
+
+    let context = scope.get_current_context();
+    let wrote = serializer.write_value(context, value).unwrap_or(false);
+
+    if !wrote {
+        return None;
+    }
+
+    Some((serializer.release(), shared_array_buffers))
+}
+
+/// Inverse of [`serialize_value`]. `shared_array_buffers` must be the list
+/// [`serialize_value`] returned alongside `bytes` (an empty list if the
+/// value being deserialized is known not to transfer any `SharedArrayBuffer`).
+pub fn deserialize_value<'s>(
+    scope: &mut v8::HandleScope<'s>,
+    bytes: &[u8],
+    shared_array_buffers: SharedArrayBuffers,
+) -> Option<v8::Local<'s, v8::Value>> {
+    let mut deserializer = v8::ValueDeserializer::new(scope, Box::new(Deserializer { shared_array_buffers }), bytes);
+    let context = scope.get_current_context();
+
+    deserializer.read_header(context).ok()?;
+    deserializer.read_value(context)
+}
@@ -0,0 +1,58 @@
+use std::sync::OnceLock;
+
+use v8::ExternalReference;
+use v8::ExternalReferences;
+use v8::MapFnTo;
+
+use super::opcall;
+use super::GLOBAL_FUNCTIONS;
+
+/// A V8 startup snapshot blob, modeled on deno_core's `Snapshot` enum: a
+/// blob can be compiled into the binary, freshly produced by
+/// [`super::JsRuntime::snapshot`], or owned on the heap (e.g. read from disk).
+pub enum Snapshot {
+    Static(&'static [u8]),
+    JustCreated(v8::StartupData),
+    Boxed(Box<[u8]>),
+}
+
+impl Snapshot {
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Snapshot::Static(data) => data,
+            Snapshot::JustCreated(data) => data.as_ref(),
+            Snapshot::Boxed(data) => data,
+        }
+    }
+}
+
+/// External references shared by snapshot creation and snapshot
+/// deserialization: every native function [`super::bootstrap`] installs, so
+/// it must resolve to the same pointers in both passes or V8 refuses to
+/// (de)serialize the blob. Built from [`GLOBAL_FUNCTIONS`] plus `opcall`
+/// (installed separately since it's nested under `Deno.core`) rather than a
+/// second, hand-maintained list, so a native function added to bootstrap
+/// can't be forgotten here.
+///
+/// Cached in a `OnceLock` and handed out by `&'static` reference: V8 holds
+/// onto this table for as long as the isolate it was used to create exists,
+/// so building a fresh one (and leaking it) on every `snapshot()`/
+/// `from_snapshot()` call would leak unboundedly over a long-running host's
+/// lifetime.
+pub(super) fn external_references() -> &'static ExternalReferences {
+    static EXTERNAL_REFERENCES: OnceLock<ExternalReferences> = OnceLock::new();
+
+    EXTERNAL_REFERENCES.get_or_init(|| {
+        let refs: Vec<ExternalReference> = GLOBAL_FUNCTIONS
+            .iter()
+            .map(|(_, function)| ExternalReference {
+                function: function.map_fn_to(),
+            })
+            .chain(std::iter::once(ExternalReference {
+                function: opcall.map_fn_to(),
+            }))
+            .collect();
+
+        ExternalReferences::new(&refs)
+    })
+}
@@ -0,0 +1,105 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::time::Instant;
+
+pub type TimerId = u32;
+
+struct Timer {
+    id: TimerId,
+    deadline: Instant,
+    callback: v8::Global<v8::Function>,
+    /// `Some(interval)` for `setInterval`, re-armed after firing.
+    repeat: Option<std::time::Duration>,
+}
+
+// `BinaryHeap` is a max-heap; reverse the ordering on `deadline` so the
+// soonest timer sorts to the top.
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for Timer {}
+
+/// Backs `setTimeout`/`setInterval`/`clearTimeout`: a min-heap of pending
+/// timers keyed by expiry instant, drained by [`super::JsRuntime::run_event_loop`].
+#[derive(Default)]
+pub struct Timers {
+    heap: BinaryHeap<Timer>,
+    next_id: TimerId,
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Schedule a new timer and return the id JS can later pass to `clearTimeout`.
+    pub fn add(
+        &mut self,
+        callback: v8::Global<v8::Function>,
+        delay: std::time::Duration,
+        repeat: bool,
+    ) -> TimerId {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+
+        self.heap.push(Timer {
+            id,
+            deadline: Instant::now() + delay,
+            callback,
+            repeat: repeat.then_some(delay),
+        });
+
+        id
+    }
+
+    /// Remove a timer before it fires (`clearTimeout`/`clearInterval`). No-op
+    /// if the id is unknown or already fired.
+    pub fn remove(&mut self, id: TimerId) {
+        self.heap.retain(|timer| timer.id != id);
+    }
+
+    /// Pop every timer whose deadline has passed, re-arming repeating ones.
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<v8::Global<v8::Function>> {
+        let mut ready = Vec::new();
+
+        while matches!(self.heap.peek(), Some(timer) if timer.deadline <= now) {
+            let timer = self.heap.pop().unwrap();
+            ready.push(timer.callback.clone());
+
+            if let Some(interval) = timer.repeat {
+                self.heap.push(Timer {
+                    id: timer.id,
+                    deadline: now + interval,
+                    callback: timer.callback,
+                    repeat: Some(interval),
+                });
+            }
+        }
+
+        ready
+    }
+
+    /// Instant at which the loop should next wake to service timers.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.heap.peek().map(|timer| timer.deadline)
+    }
+}